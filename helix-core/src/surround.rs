@@ -1,7 +1,7 @@
-use crate::{search, Selection};
+use crate::{search, Range, Selection};
 use ropey::RopeSlice;
 
-pub const PAIRS: &[(char, char)] = &[
+pub const DEFAULT_PAIRS: &[(char, char)] = &[
     ('(', ')'),
     ('[', ']'),
     ('{', '}'),
@@ -11,18 +11,54 @@ pub const PAIRS: &[(char, char)] = &[
     ('（', '）'),
 ];
 
-/// Given any char in [PAIRS], return the open and closing chars. If not found in
-/// [PAIRS] return (ch, ch).
+/// The set of surround delimiter pairs in effect for a buffer. Defaults to
+/// [DEFAULT_PAIRS].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurroundConfig {
+    pub pairs: Vec<(char, char)>,
+}
+
+impl Default for SurroundConfig {
+    fn default() -> Self {
+        Self {
+            pairs: DEFAULT_PAIRS.to_vec(),
+        }
+    }
+}
+
+impl SurroundConfig {
+    /// Build a config from [DEFAULT_PAIRS] with `overrides` merged on top. A pair
+    /// sharing an open or close char with an existing pair replaces it; otherwise it
+    /// is appended.
+    pub fn with_overrides(overrides: &[(char, char)]) -> Self {
+        let mut pairs = DEFAULT_PAIRS.to_vec();
+        for &(open, close) in overrides {
+            match pairs
+                .iter_mut()
+                .find(|(o, c)| *o == open || *c == open || *o == close || *c == close)
+            {
+                Some(pair) => *pair = (open, close),
+                None => pairs.push((open, close)),
+            }
+        }
+        Self { pairs }
+    }
+}
+
+/// Given any char in `config`'s pairs, return the open and closing chars. If not found
+/// return (ch, ch).
 ///
 /// ```
-/// use helix_core::surround::get_pair;
+/// use helix_core::surround::{get_pair, SurroundConfig};
 ///
-/// assert_eq!(get_pair('['), ('[', ']'));
-/// assert_eq!(get_pair('}'), ('{', '}'));
-/// assert_eq!(get_pair('"'), ('"', '"'));
+/// let config = SurroundConfig::default();
+/// assert_eq!(get_pair(&config, '['), ('[', ']'));
+/// assert_eq!(get_pair(&config, '}'), ('{', '}'));
+/// assert_eq!(get_pair(&config, '"'), ('"', '"'));
 /// ```
-pub fn get_pair(ch: char) -> (char, char) {
-    PAIRS
+pub fn get_pair(config: &SurroundConfig, ch: char) -> (char, char) {
+    config
+        .pairs
         .iter()
         .find(|(open, close)| *open == ch || *close == ch)
         .copied()
@@ -31,8 +67,13 @@ pub fn get_pair(ch: char) -> (char, char) {
 
 /// Find the position of balanced surround pairs of `ch` which can be either a closing
 /// or opening pair.
-pub fn find_balanced_pairs_pos(text: RopeSlice, ch: char, pos: usize) -> Option<(usize, usize)> {
-    let (open, close) = get_pair(ch);
+pub fn find_balanced_pairs_pos(
+    config: &SurroundConfig,
+    text: RopeSlice,
+    ch: char,
+    pos: usize,
+) -> Option<(usize, usize)> {
+    let (open, close) = get_pair(config, ch);
 
     let starting_pos = pos;
     let mut pos = pos;
@@ -77,12 +118,13 @@ pub fn find_balanced_pairs_pos(text: RopeSlice, ch: char, pos: usize) -> Option<
 /// or opening pair. `n` will skip n - 1 pairs (eg. n=2 will discard (only)
 /// the first pair found and keep looking)
 pub fn find_nth_pairs_pos(
+    config: &SurroundConfig,
     text: RopeSlice,
     ch: char,
     pos: usize,
     n: usize,
 ) -> Option<(usize, usize)> {
-    let (open, close) = get_pair(ch);
+    let (open, close) = get_pair(config, ch);
     // find_nth* do not consider current character; +1/-1 to include them
     let open_pos = search::find_nth_prev(text, open, pos + 1, n, true)?;
     let close_pos = search::find_nth_next(text, close, pos - 1, n, true)?;
@@ -90,11 +132,97 @@ pub fn find_nth_pairs_pos(
     Some((open_pos, close_pos))
 }
 
+/// Find the innermost balanced pair enclosing `pos`, trying every delimiter type in
+/// `config`'s pairs and keeping the one with the smallest span.
+pub fn find_nearest_surround_pos(
+    config: &SurroundConfig,
+    text: RopeSlice,
+    pos: usize,
+) -> Option<(usize, usize)> {
+    config
+        .pairs
+        .iter()
+        .filter_map(|&(open, _)| find_balanced_pairs_pos(config, text, open, pos))
+        .min_by_key(|(open_pos, close_pos)| close_pos - open_pos)
+}
+
+/// HTML5 void elements, which are never closed even without an explicit `/>`.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+    "source", "track", "wbr",
+];
+
+/// Find the innermost matching HTML/XML tag pair enclosing `pos`, e.g. `<div>...</div>`.
+/// Returns the range of the opening tag and the range of the closing tag, so callers can
+/// rename both (`div` -> `span`) or delete them independently. Nested tags with the same
+/// name are balanced correctly, self-closing (`<br/>`) and void (`<br>`) elements are
+/// skipped, and only the tag name, up to the first whitespace, `/` or `>`, is matched.
+pub fn find_surrounding_tag(text: RopeSlice, pos: usize) -> Option<(Range, Range)> {
+    struct OpenTag {
+        name: String,
+        range: Range,
+    }
+
+    let mut stack: Vec<OpenTag> = Vec::new();
+    let mut chars = text.chars().enumerate().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '<' {
+            continue;
+        }
+
+        let closing = chars.next_if(|&(_, c)| c == '/').is_some();
+
+        let mut name = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c.is_whitespace() || c == '>' || c == '/' {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut self_closing = false;
+        let mut end = start;
+        let mut prev = '\0';
+        for (i, c) in chars.by_ref() {
+            end = i;
+            if c == '>' {
+                self_closing = prev == '/';
+                break;
+            }
+            prev = c;
+        }
+
+        if closing {
+            if matches!(stack.last(), Some(top) if top.name == name) {
+                let open = stack.pop().unwrap();
+                let close_range = Range::new(start, end + 1);
+                if open.range.from() <= pos && pos < close_range.to() {
+                    return Some((open.range, close_range));
+                }
+            }
+        } else if !self_closing && !VOID_ELEMENTS.contains(&name.as_str()) {
+            stack.push(OpenTag {
+                name,
+                range: Range::new(start, end + 1),
+            });
+        }
+    }
+
+    None
+}
+
 /// Find position of surround characters around every cursor. Returns None
 /// if any positions overlap. Note that the positions are in a flat Vec.
 /// Use get_surround_pos().chunks(2) to get matching pairs of surround positions.
 /// `ch` can be either closing or opening pair.
 pub fn get_surround_pos(
+    config: &SurroundConfig,
     text: RopeSlice,
     selection: &Selection,
     ch: char,
@@ -103,7 +231,7 @@ pub fn get_surround_pos(
     let mut change_pos = Vec::new();
 
     for range in selection {
-        let (open_pos, close_pos) = find_balanced_pairs_pos(text, ch, range.head)?;
+        let (open_pos, close_pos) = find_balanced_pairs_pos(config, text, ch, range.head)?;
         if change_pos.contains(&open_pos) || change_pos.contains(&close_pos) {
             return None;
         }
@@ -112,6 +240,60 @@ pub fn get_surround_pos(
     Some(change_pos)
 }
 
+/// Trim whitespace just inside a balanced pair's `open_pos`/`close_pos` (as returned by
+/// [find_balanced_pairs_pos]) and return the resulting inner span. `tight` trims at most
+/// one leading and trailing whitespace character; otherwise all of it is trimmed.
+pub fn trim_surround_pos(
+    text: RopeSlice,
+    open_pos: usize,
+    close_pos: usize,
+    tight: bool,
+) -> (usize, usize) {
+    let mut start = open_pos + 1;
+    let mut end = close_pos;
+
+    if tight {
+        if start < end && text.char(start).is_whitespace() {
+            start += 1;
+        }
+        if end > start && text.char(end - 1).is_whitespace() {
+            end -= 1;
+        }
+    } else {
+        while start < end && text.char(start).is_whitespace() {
+            start += 1;
+        }
+        while end > start && text.char(end - 1).is_whitespace() {
+            end -= 1;
+        }
+    }
+
+    (start, end)
+}
+
+/// Like [get_surround_pos], but returns each pair's trimmed inner span (see
+/// [trim_surround_pos]) instead of the delimiter positions themselves.
+pub fn get_surround_pos_trimmed(
+    config: &SurroundConfig,
+    text: RopeSlice,
+    selection: &Selection,
+    ch: char,
+    skip: usize,
+    tight: bool,
+) -> Option<Vec<usize>> {
+    let change_pos = get_surround_pos(config, text, selection, ch, skip)?;
+
+    Some(
+        change_pos
+            .chunks(2)
+            .flat_map(|pair| {
+                let (start, end) = trim_surround_pos(text, pair[0], pair[1], tight);
+                [start, end]
+            })
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -120,18 +302,34 @@ mod test {
     use ropey::Rope;
     use smallvec::SmallVec;
 
+    fn config() -> SurroundConfig {
+        SurroundConfig::default()
+    }
+
+    #[test]
+    fn test_surround_config_with_overrides() {
+        let config = SurroundConfig::with_overrides(&[('«', '»'), ('‘', '’')]);
+
+        // an override sharing a char with a default pair replaces it in place...
+        assert!(config.pairs.contains(&('«', '»')));
+        assert_eq!(config.pairs.iter().filter(|(o, _)| *o == '«').count(), 1);
+        // ...while one with no overlap is appended
+        assert!(config.pairs.contains(&('‘', '’')));
+        assert_eq!(config.pairs.len(), DEFAULT_PAIRS.len() + 1);
+    }
+
     #[test]
     fn test_find_nth_pairs_pos() {
         let doc = Rope::from("some (text) here");
         let slice = doc.slice(..);
 
         // cursor on [t]ext
-        assert_eq!(find_nth_pairs_pos(slice, '(', 6, 1), Some((5, 10)));
-        assert_eq!(find_nth_pairs_pos(slice, ')', 6, 1), Some((5, 10)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 6, 1), Some((5, 10)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, ')', 6, 1), Some((5, 10)));
         // cursor on so[m]e
-        assert_eq!(find_nth_pairs_pos(slice, '(', 2, 1), None);
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 2, 1), None);
         // cursor on bracket itself
-        assert_eq!(find_nth_pairs_pos(slice, '(', 5, 1), Some((5, 10)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 5, 1), Some((5, 10)));
     }
 
     #[test]
@@ -140,20 +338,20 @@ mod test {
         let slice = doc.slice(..);
 
         // cursor on [t]ext
-        assert_eq!(find_balanced_pairs_pos(slice, '(', 7), Some((6, 11)));
-        assert_eq!(find_balanced_pairs_pos(slice, ')', 7), Some((6, 11)));
+        assert_eq!(find_balanced_pairs_pos(&config(), slice, '(', 7), Some((6, 11)));
+        assert_eq!(find_balanced_pairs_pos(&config(), slice, ')', 7), Some((6, 11)));
         // cursor on so[m]e
-        assert_eq!(find_balanced_pairs_pos(slice, '(', 2), None);
+        assert_eq!(find_balanced_pairs_pos(&config(), slice, '(', 2), None);
         // cursor on bracket itself
-        assert_eq!(find_balanced_pairs_pos(slice, '(', 6), Some((6, 11)));
+        assert_eq!(find_balanced_pairs_pos(&config(), slice, '(', 6), Some((6, 11)));
         // cursor on outer parens
-        assert_eq!(find_balanced_pairs_pos(slice, '(', 5), Some((5, 17)));
+        assert_eq!(find_balanced_pairs_pos(&config(), slice, '(', 5), Some((5, 17)));
 
         let doc = Rope::from("some (text (here))");
         let slice = doc.slice(..);
 
         // cursor on outer parens
-        assert_eq!(find_balanced_pairs_pos(slice, '(', 17), Some((5, 17)));
+        assert_eq!(find_balanced_pairs_pos(&config(), slice, '(', 17), Some((5, 17)));
     }
 
     #[test]
@@ -162,9 +360,9 @@ mod test {
         let slice = doc.slice(..);
 
         // cursor on go[o]d
-        assert_eq!(find_nth_pairs_pos(slice, '(', 13, 1), Some((10, 15)));
-        assert_eq!(find_nth_pairs_pos(slice, '(', 13, 2), Some((4, 21)));
-        assert_eq!(find_nth_pairs_pos(slice, '(', 13, 3), Some((0, 27)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 13, 1), Some((10, 15)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 13, 2), Some((4, 21)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 13, 3), Some((0, 27)));
     }
 
     #[test]
@@ -173,9 +371,64 @@ mod test {
         let slice = doc.slice(..);
 
         // cursor on go[o]d
-        assert_eq!(find_nth_pairs_pos(slice, '{', 13, 1), Some((10, 15)));
-        assert_eq!(find_nth_pairs_pos(slice, '[', 13, 1), Some((4, 21)));
-        assert_eq!(find_nth_pairs_pos(slice, '(', 13, 1), Some((0, 27)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '{', 13, 1), Some((10, 15)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '[', 13, 1), Some((4, 21)));
+        assert_eq!(find_nth_pairs_pos(&config(), slice, '(', 13, 1), Some((0, 27)));
+    }
+
+    #[test]
+    fn test_find_nearest_surround_pos() {
+        let doc = Rope::from("(so [many {good} text] here)");
+        let slice = doc.slice(..);
+
+        // cursor on go[o]d picks the innermost `{}` over the enclosing `[]` and `()`
+        assert_eq!(find_nearest_surround_pos(&config(), slice, 13), Some((10, 15)));
+        // cursor on m[a]ny picks `[]` over the enclosing `()`
+        assert_eq!(find_nearest_surround_pos(&config(), slice, 6), Some((4, 21)));
+    }
+
+    #[test]
+    fn test_find_surrounding_tag() {
+        let doc = Rope::from("<div><span>good</span></div>");
+        let slice = doc.slice(..);
+
+        // cursor on go[o]d picks the innermost <span> over the enclosing <div>
+        assert_eq!(
+            find_surrounding_tag(slice, 13),
+            Some((Range::new(5, 11), Range::new(15, 22)))
+        );
+        // cursor on <[d]iv picks the outer <div>, since it is not inside <span>
+        assert_eq!(
+            find_surrounding_tag(slice, 1),
+            Some((Range::new(0, 5), Range::new(22, 28)))
+        );
+
+        let doc = Rope::from("<p>one<br/>two</p>");
+        let slice = doc.slice(..);
+
+        // self-closing <br/> is not a surrounding pair
+        assert_eq!(
+            find_surrounding_tag(slice, 8),
+            Some((Range::new(0, 3), Range::new(14, 18)))
+        );
+
+        let doc = Rope::from("<a href=\"http://x.com/foo\">text</a>");
+        let slice = doc.slice(..);
+
+        // a `/` inside an attribute value doesn't trigger false self-closing detection
+        assert_eq!(
+            find_surrounding_tag(slice, 30),
+            Some((Range::new(0, 27), Range::new(31, 35)))
+        );
+
+        let doc = Rope::from("<p>one<br>two</p>");
+        let slice = doc.slice(..);
+
+        // a void element without an explicit `/>` doesn't poison its ancestor's balance
+        assert_eq!(
+            find_surrounding_tag(slice, 4),
+            Some((Range::new(0, 3), Range::new(13, 17)))
+        );
     }
 
     #[test]
@@ -189,13 +442,51 @@ mod test {
 
         // cursor on s[o]me, c[h]ars, newl[i]ne
         assert_eq!(
-            get_surround_pos(slice, &selection, '(', 1)
+            get_surround_pos(&config(), slice, &selection, '(', 1)
                 .unwrap()
                 .as_slice(),
             &[0, 5, 7, 13, 15, 23]
         );
     }
 
+    #[test]
+    fn test_trim_surround_pos() {
+        let doc = Rope::from("( foo )  (  )");
+        let slice = doc.slice(..);
+
+        // full trim removes all inner whitespace
+        assert_eq!(trim_surround_pos(slice, 0, 6, false), (2, 5));
+        // tight trim removes only one space on each side
+        assert_eq!(trim_surround_pos(slice, 0, 6, true), (2, 5));
+
+        // an interior that is entirely whitespace collapses to an empty span, whether
+        // trimmed fully or one space at a time
+        assert_eq!(trim_surround_pos(slice, 9, 12, false), (12, 12));
+        assert_eq!(trim_surround_pos(slice, 9, 12, true), (11, 11));
+
+        let doc = Rope::from("(  foo  )");
+        let slice = doc.slice(..);
+
+        // tight trim leaves the extra whitespace that full trim would remove
+        assert_eq!(trim_surround_pos(slice, 0, 8, false), (3, 6));
+        assert_eq!(trim_surround_pos(slice, 0, 8, true), (2, 7));
+    }
+
+    #[test]
+    fn test_get_surround_pos_trimmed() {
+        let doc = Rope::from("( foo )");
+        let slice = doc.slice(..);
+        let selection = Selection::new(SmallVec::from_slice(&[Range::point(3)]), 0);
+
+        // cursor on f[o]o
+        assert_eq!(
+            get_surround_pos_trimmed(&config(), slice, &selection, '(', 1, false)
+                .unwrap()
+                .as_slice(),
+            &[2, 5]
+        );
+    }
+
     #[test]
     fn test_get_surround_pos_bail() {
         let doc = Rope::from("[some]\n(chars)xx\n(newline)");
@@ -206,7 +497,7 @@ mod test {
 
         // cursor on s[o]me, c[h]ars
         assert_eq!(
-            get_surround_pos(slice, &selection, '(', 1),
+            get_surround_pos(&config(), slice, &selection, '(', 1),
             None // different surround chars
         );
 
@@ -216,7 +507,7 @@ mod test {
         );
         // cursor on [x]x, newli[n]e
         assert_eq!(
-            get_surround_pos(slice, &selection, '(', 1),
+            get_surround_pos(&config(), slice, &selection, '(', 1),
             None // overlapping surround chars
         );
     }